@@ -0,0 +1,3 @@
+mod oidc;
+
+pub use oidc::{login, IdentityConfig, OAuthTokens};
@@ -0,0 +1,295 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::time::Duration as StdDuration;
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use crate::graphql::client::ClientError;
+
+/// OIDC discovery document for the realm being authenticated against.
+pub struct IdentityConfig {
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub client_id: String,
+}
+
+#[derive(Deserialize)]
+struct DiscoveryDocument {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    client_id: String,
+}
+
+/// Access/refresh token pair returned by the authorization-code-with-PKCE flow.
+pub struct OAuthTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    // Per RFC 6749 §6, a refresh response MAY omit `refresh_token` when the
+    // server doesn't rotate it; keep the existing one in that case.
+    refresh_token: Option<String>,
+    expires_in: i64,
+}
+
+impl OAuthTokens {
+    fn needs_refresh(&self) -> bool {
+        self.expires_at - Utc::now() <= Duration::seconds(60)
+    }
+
+    fn apply(&mut self, response: TokenResponse) {
+        self.access_token = response.access_token;
+        if let Some(refresh_token) = response.refresh_token {
+            self.refresh_token = refresh_token;
+        }
+        self.expires_at = Utc::now() + Duration::seconds(response.expires_in);
+    }
+
+    /// Refreshes the access token in place if it is within ~60s of expiring.
+    pub fn refresh_if_needed(&mut self, identity: &IdentityConfig) -> Result<(), ClientError> {
+        if !self.needs_refresh() {
+            return Ok(());
+        }
+
+        let client = reqwest::blocking::Client::new();
+        let response: TokenResponse = client.post(&identity.token_endpoint)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", &self.refresh_token),
+                ("client_id", &identity.client_id),
+            ])
+            .send()?
+            .json()?;
+
+        self.apply(response);
+        Ok(())
+    }
+
+    /// Async counterpart of [`OAuthTokens::refresh_if_needed`], used by
+    /// [`crate::graphql::async_client::AsyncApolloCloudClient`].
+    pub async fn refresh_if_needed_async(&mut self, identity: &IdentityConfig) -> Result<(), ClientError> {
+        if !self.needs_refresh() {
+            return Ok(());
+        }
+
+        let client = reqwest::Client::new();
+        let response: TokenResponse = client.post(&identity.token_endpoint)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", &self.refresh_token),
+                ("client_id", &identity.client_id),
+            ])
+            .send().await?
+            .json().await?;
+
+        self.apply(response);
+        Ok(())
+    }
+}
+
+/// Fetches the OIDC discovery document for `realm`.
+pub fn discover_identity_config(realm: &str) -> Result<IdentityConfig, ClientError> {
+    let url = format!("{}/.well-known/openid-configuration", realm.trim_end_matches('/'));
+    let doc: DiscoveryDocument = reqwest::blocking::get(&url)?.json()?;
+    Ok(IdentityConfig {
+        authorization_endpoint: doc.authorization_endpoint,
+        token_endpoint: doc.token_endpoint,
+        client_id: doc.client_id,
+    })
+}
+
+fn generate_code_verifier() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+/// Generates an opaque value to guard the redirect against CSRF: `login`
+/// sends it in `auth_url` and `catch_redirect` rejects any redirect whose
+/// `state` doesn't match.
+fn generate_state() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+fn code_challenge(verifier: &str) -> String {
+    let digest = Sha256::digest(verifier.as_bytes());
+    base64::encode_config(digest, base64::URL_SAFE_NO_PAD)
+}
+
+/// How long to wait for the browser to redirect back before giving up on the
+/// login flow (e.g. the user closed the tab or denied consent).
+const LOGIN_TIMEOUT: StdDuration = StdDuration::from_secs(300);
+const POLL_INTERVAL: StdDuration = StdDuration::from_millis(200);
+
+/// Waits for a single redirect on `listener` and extracts the `code` query
+/// parameter, rejecting the redirect unless its `state` matches `expected_state`.
+///
+/// Gives up with `OAuthFlowFailed` after [`LOGIN_TIMEOUT`] if no redirect arrives.
+fn catch_redirect(listener: TcpListener, expected_state: &str) -> Result<String, ClientError> {
+    listener.set_nonblocking(true)?;
+    let deadline = std::time::Instant::now() + LOGIN_TIMEOUT;
+
+    let mut stream = loop {
+        match listener.accept() {
+            Ok((stream, _)) => break stream,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                if std::time::Instant::now() >= deadline {
+                    return Err(ClientError::OAuthFlowFailed(String::from(
+                        "timed out waiting for the browser login redirect")));
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => return Err(ClientError::Io(e)),
+        }
+    };
+    stream.set_nonblocking(false)?;
+
+    let mut buf = [0u8; 4096];
+    let n = stream.read(&mut buf)?;
+    let request_line = String::from_utf8_lossy(&buf[..n]);
+    let path = request_line
+        .lines().next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .ok_or_else(|| ClientError::OAuthFlowFailed(String::from("malformed redirect request")))?;
+
+    let query = path.split_once('?').map(|(_, query)| query)
+        .ok_or_else(|| ClientError::OAuthFlowFailed(String::from("redirect was missing a query string")))?;
+    let params: Vec<(String, String)> = url::form_urlencoded::parse(query.as_bytes())
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    let state = params.iter().find(|(key, _)| key == "state").map(|(_, value)| value.as_str());
+    if state != Some(expected_state) {
+        return Err(ClientError::OAuthFlowFailed(String::from(
+            "redirect state did not match the login attempt; possible CSRF")));
+    }
+
+    let code = params.into_iter()
+        .find(|(key, _)| key == "code")
+        .map(|(_, value)| value)
+        .ok_or_else(|| ClientError::OAuthFlowFailed(String::from("redirect was missing the authorization code")))?;
+
+    let body = "<html><body>Authenticated, you can close this tab.</body></html>";
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nContent-Type: text/html\r\n\r\n{}",
+        body.len(), body
+    );
+    stream.write_all(response.as_bytes())?;
+
+    Ok(code)
+}
+
+fn exchange_code_for_tokens(identity: &IdentityConfig, code: &str, code_verifier: &str, redirect_uri: &str) -> Result<OAuthTokens, ClientError> {
+    let client = reqwest::blocking::Client::new();
+    let response: TokenResponse = client.post(&identity.token_endpoint)
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("client_id", &identity.client_id),
+            ("code_verifier", code_verifier),
+        ])
+        .send()?
+        .json()?;
+
+    let refresh_token = response.refresh_token
+        .ok_or_else(|| ClientError::OAuthFlowFailed(String::from("token endpoint did not return a refresh_token")))?;
+
+    Ok(OAuthTokens {
+        access_token: response.access_token,
+        refresh_token,
+        expires_at: Utc::now() + Duration::seconds(response.expires_in),
+    })
+}
+
+/// Runs the interactive authorization-code-with-PKCE login flow against `realm`:
+/// opens the system browser to the authorization endpoint, catches the redirect
+/// on a localhost listener, and exchanges the resulting code for tokens.
+pub fn login(realm: &str) -> Result<OAuthTokens, ClientError> {
+    let identity = discover_identity_config(realm)?;
+
+    let verifier = generate_code_verifier();
+    let challenge = code_challenge(&verifier);
+    let state = generate_state();
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let redirect_uri = format!("http://127.0.0.1:{}/callback", listener.local_addr()?.port());
+
+    let auth_url = format!(
+        "{}?response_type=code&client_id={}&redirect_uri={}&code_challenge={}&code_challenge_method=S256&state={}",
+        identity.authorization_endpoint,
+        url::form_urlencoded::byte_serialize(identity.client_id.as_bytes()).collect::<String>(),
+        url::form_urlencoded::byte_serialize(redirect_uri.as_bytes()).collect::<String>(),
+        challenge,
+        url::form_urlencoded::byte_serialize(state.as_bytes()).collect::<String>(),
+    );
+    webbrowser::open(&auth_url)
+        .map_err(|e| ClientError::OAuthFlowFailed(format!("could not open browser: {}", e)))?;
+
+    let code = catch_redirect(listener, &state)?;
+    exchange_code_for_tokens(&identity, &code, &verifier, &redirect_uri)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpStream;
+
+    #[test]
+    fn code_verifier_is_url_safe_and_unique() {
+        let a = generate_code_verifier();
+        let b = generate_code_verifier();
+        assert_ne!(a, b, "verifiers should be freshly randomized each call");
+        assert!(a.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    }
+
+    #[test]
+    fn code_challenge_is_deterministic_and_differs_from_verifier() {
+        let verifier = generate_code_verifier();
+        let challenge = code_challenge(&verifier);
+        assert_eq!(challenge, code_challenge(&verifier));
+        assert_ne!(challenge, verifier);
+    }
+
+    #[test]
+    fn state_is_unique_per_login_attempt() {
+        assert_ne!(generate_state(), generate_state());
+    }
+
+    fn send_redirect(addr: std::net::SocketAddr, query: &str) -> std::thread::JoinHandle<()> {
+        let query = String::from(query);
+        std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(format!("GET /callback?{} HTTP/1.1\r\n\r\n", query).as_bytes()).unwrap();
+        })
+    }
+
+    #[test]
+    fn catch_redirect_accepts_a_matching_state() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = send_redirect(addr, "code=abc123&state=good-state");
+
+        let code = catch_redirect(listener, "good-state").unwrap();
+        client.join().unwrap();
+        assert_eq!(code, "abc123");
+    }
+
+    #[test]
+    fn catch_redirect_rejects_a_mismatched_state() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = send_redirect(addr, "code=abc123&state=wrong-state");
+
+        let result = catch_redirect(listener, "expected-state");
+        client.join().unwrap();
+        assert!(result.is_err());
+    }
+}
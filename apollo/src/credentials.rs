@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use dirs::data_dir;
+use serde::{Deserialize, Serialize};
+use crate::graphql::client::ClientError;
+
+const DEFAULT_PROFILE: &str = "default";
+const APOLLO_KEY_ENV_VAR: &str = "APOLLO_KEY";
+const DEFAULT_REGISTRY_URL: &str = "https://graphql.api.apollographql.com/api/graphql";
+
+/// A named set of credentials for one endpoint, persisted under `data_dir()`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Credential {
+    pub endpoint_url: String,
+    pub auth: StoredAuth,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum StoredAuth {
+    ApiKey {
+        token: String,
+    },
+    OAuth {
+        access_token: String,
+        refresh_token: String,
+        expires_at: DateTime<Utc>,
+        client_id: String,
+        authorization_endpoint: String,
+        token_endpoint: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct ProfileStore {
+    #[serde(flatten)]
+    profiles: HashMap<String, Credential>,
+}
+
+fn credentials_path() -> Result<PathBuf, ClientError> {
+    let dir = data_dir()
+        .ok_or_else(|| ClientError::NotFound(String::from("could not determine the user data directory")))?
+        .join("federation");
+    Ok(dir.join("profiles.toml"))
+}
+
+fn load_store() -> Result<ProfileStore, ClientError> {
+    let path = credentials_path()?;
+    if !path.exists() {
+        return Ok(ProfileStore::default());
+    }
+    let contents = fs::read_to_string(path)?;
+    toml::from_str(&contents).map_err(|e| ClientError::NotFound(format!("malformed credential store: {}", e)))
+}
+
+fn save_store(store: &ProfileStore) -> Result<(), ClientError> {
+    let path = credentials_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = toml::to_string_pretty(store)
+        .map_err(|e| ClientError::NotFound(format!("could not serialize credential store: {}", e)))?;
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Saves `credential` under `name`, creating or overwriting the profile.
+pub fn save_profile(name: &str, credential: Credential) -> Result<(), ClientError> {
+    let mut store = load_store()?;
+    store.profiles.insert(String::from(name), credential);
+    save_store(&store)
+}
+
+/// Loads the credential for `name`, applying the `APOLLO_KEY` environment
+/// override if it is set.
+///
+/// `APOLLO_KEY` takes precedence even when no profile named `name` has ever
+/// been saved: the endpoint URL is taken from the stored profile if one
+/// exists, and otherwise falls back to [`DEFAULT_REGISTRY_URL`].
+pub fn load_profile(name: &str) -> Result<Credential, ClientError> {
+    let store = load_store()?;
+    let stored = store.profiles.get(name).cloned();
+
+    if let Ok(key) = std::env::var(APOLLO_KEY_ENV_VAR) {
+        let endpoint_url = stored.map(|c| c.endpoint_url)
+            .unwrap_or_else(|| String::from(DEFAULT_REGISTRY_URL));
+        return Ok(Credential { endpoint_url, auth: StoredAuth::ApiKey { token: key } });
+    }
+
+    stored.ok_or_else(|| ClientError::NotFound(format!("no credential profile named '{}'", name)))
+}
+
+pub(crate) fn default_profile_name() -> &'static str {
+    DEFAULT_PROFILE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `credentials_path` resolves through `dirs::data_dir()`, which honors
+    // `XDG_DATA_HOME` on Linux. Pointing it at a scratch directory keeps
+    // these tests from touching a real user's credential store. Env vars are
+    // process-global, so everything that depends on them lives in this one
+    // test to avoid racing with itself under the parallel test runner.
+    #[test]
+    fn api_key_override_and_round_trip() {
+        let scratch = std::env::temp_dir().join(format!("federation-creds-test-{}", std::process::id()));
+        std::env::set_var("XDG_DATA_HOME", &scratch);
+
+        let stored = Credential {
+            endpoint_url: String::from("https://example.com/graphql"),
+            auth: StoredAuth::ApiKey { token: String::from("stored-token") },
+        };
+        save_profile("work", stored.clone()).unwrap();
+
+        // Round trip: no APOLLO_KEY set, the saved profile comes back as-is.
+        std::env::remove_var(APOLLO_KEY_ENV_VAR);
+        let loaded = load_profile("work").unwrap();
+        assert_eq!(loaded.endpoint_url, stored.endpoint_url);
+        match loaded.auth {
+            StoredAuth::ApiKey { token } => assert_eq!(token, "stored-token"),
+            StoredAuth::OAuth { .. } => panic!("expected ApiKey"),
+        }
+
+        // APOLLO_KEY takes precedence over a saved profile's token, but the
+        // profile's endpoint URL is preserved.
+        std::env::set_var(APOLLO_KEY_ENV_VAR, "env-token");
+        let overridden = load_profile("work").unwrap();
+        assert_eq!(overridden.endpoint_url, stored.endpoint_url);
+        match overridden.auth {
+            StoredAuth::ApiKey { token } => assert_eq!(token, "env-token"),
+            StoredAuth::OAuth { .. } => panic!("expected ApiKey"),
+        }
+
+        // With no profile saved under this name at all, APOLLO_KEY still
+        // resolves a usable credential against the default registry.
+        let synthesized = load_profile("never-saved").unwrap();
+        assert_eq!(synthesized.endpoint_url, DEFAULT_REGISTRY_URL);
+
+        std::env::remove_var(APOLLO_KEY_ENV_VAR);
+        assert!(load_profile("never-saved").is_err());
+
+        std::env::remove_var("XDG_DATA_HOME");
+        let _ = fs::remove_dir_all(&scratch);
+    }
+}
@@ -0,0 +1,323 @@
+use std::future::Future;
+use futures::stream::{self, StreamExt};
+use reqwest::{Client, StatusCode};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::iter::FromIterator;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Duration};
+
+use crate::auth::{IdentityConfig, OAuthTokens};
+use crate::credentials::StoredAuth;
+use crate::graphql::client::ClientError;
+use crate::graphql::types::*;
+
+const MAX_RETRIES: u32 = 5;
+const BASE_BACKOFF_MS: u64 = 250;
+/// Default bound on in-flight requests for [`AsyncApolloCloudClient::execute_batch`].
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 8;
+
+enum AuthState {
+    ApiKey(String),
+    OAuth { tokens: OAuthTokens, identity: IdentityConfig },
+}
+
+/// Non-blocking counterpart of [`crate::graphql::client::ApolloCloudClient`],
+/// built on `reqwest::Client` so many operations (e.g. fetching every subgraph
+/// of a federation) can run concurrently instead of one thread each.
+pub struct AsyncApolloCloudClient {
+    endpoint_url: String,
+    client: Client,
+    auth: Mutex<AuthState>,
+}
+
+#[derive(Serialize)]
+struct GraphqlQuery<'a, V: Serialize> {
+    query: &'a str,
+    variables: Option<V>,
+}
+
+impl AsyncApolloCloudClient {
+    pub fn new(endpoint_url: String, auth_token: String) -> AsyncApolloCloudClient {
+        AsyncApolloCloudClient {
+            endpoint_url,
+            client: Client::new(),
+            auth: Mutex::new(AuthState::ApiKey(auth_token)),
+        }
+    }
+
+    pub fn from_oauth(endpoint_url: String, identity: IdentityConfig, tokens: OAuthTokens) -> AsyncApolloCloudClient {
+        AsyncApolloCloudClient {
+            endpoint_url,
+            client: Client::new(),
+            auth: Mutex::new(AuthState::OAuth { tokens, identity }),
+        }
+    }
+
+    pub fn endpoint_url(&self) -> &str {
+        &self.endpoint_url
+    }
+
+    /// A snapshot of this client's current credentials, suitable for
+    /// persisting via [`crate::credentials::save_profile`].
+    pub async fn snapshot_auth(&self) -> StoredAuth {
+        match &*self.auth.lock().await {
+            AuthState::ApiKey(token) => StoredAuth::ApiKey { token: token.clone() },
+            AuthState::OAuth { tokens, identity } => StoredAuth::OAuth {
+                access_token: tokens.access_token.clone(),
+                refresh_token: tokens.refresh_token.clone(),
+                expires_at: tokens.expires_at,
+                client_id: identity.client_id.clone(),
+                authorization_endpoint: identity.authorization_endpoint.clone(),
+                token_endpoint: identity.token_endpoint.clone(),
+            },
+        }
+    }
+
+    async fn auth_header(&self) -> Result<(&'static str, String), ClientError> {
+        match &mut *self.auth.lock().await {
+            AuthState::ApiKey(key) => Ok(("X-API-KEY", key.clone())),
+            AuthState::OAuth { tokens, identity } => {
+                tokens.refresh_if_needed_async(identity).await?;
+                Ok(("AUTHORIZATION", format!("Bearer {}", tokens.access_token)))
+            }
+        }
+    }
+
+    fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+        response.headers().get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(Duration::from_secs)
+    }
+
+    fn backoff(attempt: u32) -> Duration {
+        Duration::from_millis(BASE_BACKOFF_MS * 2u64.saturating_pow(attempt))
+    }
+
+    async fn send_query<T: DeserializeOwned, V: Serialize>(&self, query: GraphqlQuery<'_, V>) -> Result<T, ClientError> {
+        let query_body = serde_json::to_string(&query)?;
+
+        let mut attempt = 0;
+        loop {
+            // Recomputed every attempt (not hoisted above the loop): a long
+            // retry run can outlive the access token, and `auth_header`
+            // refreshes it transparently when it's close to expiring.
+            let (header_name, header_value) = self.auth_header().await?;
+            let response = self.client.post(&self.endpoint_url)
+                .header(header_name, &header_value)
+                .header("CONTENT-TYPE", "application/json")
+                .body(query_body.clone())
+                .send().await?;
+
+            let status = response.status();
+            if (status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) && attempt < MAX_RETRIES {
+                let delay = Self::retry_after(&response).unwrap_or_else(|| Self::backoff(attempt));
+                sleep(delay).await;
+                attempt += 1;
+                continue;
+            }
+
+            let text = response.text().await?;
+            let result: GraphResult<T> = serde_json::from_str(&text)?;
+            if !result.errors.is_empty() {
+                let looks_like_auth_failure = result.data.is_none() &&
+                    result.errors.iter().any(|e| e.message.to_lowercase().contains("auth"));
+                if looks_like_auth_failure {
+                    return Err(ClientError::Unauthorized);
+                }
+                return Err(ClientError::GraphQl(result.errors));
+            }
+            return result.data.ok_or(ClientError::NoData);
+        }
+    }
+
+    async fn execute_operation<T: DeserializeOwned, V: Serialize>(&self, operation_string: &str, variables: V) -> Result<T, ClientError> {
+        let gql_query = GraphqlQuery { query: operation_string, variables: Some(variables) };
+        self.send_query::<T, V>(gql_query).await
+    }
+
+    async fn execute_operation_no_variables<T: DeserializeOwned>(&self, operation_string: &str) -> Result<T, ClientError> {
+        let gql_query: GraphqlQuery<()> = GraphqlQuery { query: operation_string, variables: None };
+        self.send_query::<T, ()>(gql_query).await
+    }
+
+    /// Runs `operations` concurrently, at most `concurrency` in flight at a
+    /// time, preserving the order of `operations` in the returned `Vec`.
+    /// `concurrency` of 0 is treated as 1.
+    pub async fn execute_batch<T, F>(&self, concurrency: usize, operations: Vec<F>) -> Vec<Result<T, ClientError>>
+    where F: Future<Output = Result<T, ClientError>> {
+        stream::iter(operations)
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// [`Self::execute_batch`] with [`DEFAULT_BATCH_CONCURRENCY`] in-flight requests.
+    pub async fn execute_batch_default<T, F>(&self, operations: Vec<F>) -> Vec<Result<T, ClientError>>
+    where F: Future<Output = Result<T, ClientError>> {
+        self.execute_batch(DEFAULT_BATCH_CONCURRENCY, operations).await
+    }
+
+    pub async fn get_org_memberships(&self) -> Result<HashSet<String>, ClientError> {
+        let result = self.execute_operation_no_variables::<MeData>(super::client::GET_ORG_MEMBERSHIPS_QUERY).await?;
+        match result.me {
+            Some(me) =>
+                Ok(
+                    HashSet::from_iter(
+                        me.memberships.into_iter().map(
+                            |it| it.account.id
+                        ).collect::<Vec<String>>())),
+            None => Err(ClientError::Unauthorized),
+        }
+    }
+
+    pub async fn create_new_graph(&self, graph_id: String, account_id: String) -> Result<String, ClientError> {
+        let variables = CreateGraphVariables {
+            graphID: graph_id,
+            accountID: account_id,
+        };
+        let data = self.execute_operation::<CreateGraphData, CreateGraphVariables>(super::client::CREATE_GRAPH_QUERY, variables).await?;
+        Ok(data.newService.apiKeys[0].token.clone())
+    }
+
+    /// See [`crate::graphql::client::ApolloCloudClient::fetch_subgraph_sdl`].
+    pub async fn fetch_subgraph_sdl(&self, graph_id: String, variant: String, subgraph_name: String) -> Result<SubgraphInfo, ClientError> {
+        let variables = FetchSubgraphSdlVariables {
+            serviceID: graph_id.clone(),
+            graphVariant: variant,
+        };
+        let data = self.execute_operation::<FetchSubgraphSdlData, FetchSubgraphSdlVariables>(super::client::FETCH_SUBGRAPH_SDL_QUERY, variables).await?;
+
+        let service = data.service.ok_or_else(|| ClientError::NotFound(format!("Could not find graph {}", graph_id)))?;
+
+        let services = match service.implementingServices {
+            Some(ImplementingServicesWrapper::FederatedImplementingServices { services }) => services,
+            _ => return Err(ClientError::ExpectedFederatedGraph { graph_name: graph_id }),
+        };
+
+        services.into_iter().find(|s| s.name == subgraph_name)
+            .map(|subgraph| SubgraphInfo {
+                name: subgraph.name,
+                url: subgraph.url,
+                sdl: subgraph.activePartialSchema.sdl,
+            })
+            .ok_or_else(|| ClientError::NotFound(format!("No subgraph named {} found on {}", subgraph_name, graph_id)))
+    }
+
+    /// See [`crate::graphql::client::ApolloCloudClient::publish_subgraph`].
+    pub async fn publish_subgraph(&self, graph_id: String, variant: String, subgraph_name: String, url: String, sdl: String) -> Result<PublishSubgraphResult, ClientError> {
+        let variables = PublishSubgraphVariables {
+            graphID: graph_id,
+            graphVariant: variant,
+            name: subgraph_name,
+            url,
+            activePartialSchema: ActivePartialSchemaInput { sdl },
+        };
+        let data = self.execute_operation::<PublishSubgraphData, PublishSubgraphVariables>(super::client::PUBLISH_SUBGRAPH_QUERY, variables).await?;
+
+        Ok(PublishSubgraphResult {
+            updated: data.publishSubgraph.updated,
+            composition_errors: data.publishSubgraph.errors.into_iter().map(|e| e.message).collect(),
+        })
+    }
+
+    /// See [`crate::graphql::client::ApolloCloudClient::check_subgraph`].
+    pub async fn check_subgraph(&self, graph_id: String, variant: String, subgraph_name: String, proposed_schema: String) -> Result<CheckSubgraphResult, ClientError> {
+        let variables = CheckSubgraphVariables {
+            graphID: graph_id.clone(),
+            graphVariant: variant,
+            name: subgraph_name,
+            proposedSchema: proposed_schema,
+        };
+        let data = self.execute_operation::<CheckSubgraphData, CheckSubgraphVariables>(super::client::CHECK_SUBGRAPH_QUERY, variables).await?;
+
+        let check_result = data.service
+            .ok_or_else(|| ClientError::NotFound(format!("Could not find graph {}", graph_id)))?
+            .checkPartialSchema;
+
+        Ok(CheckSubgraphResult {
+            composition_errors: check_result.compositionValidationResult.errors.into_iter().map(|e| e.message).collect(),
+            breaking_changes: check_result.checkSchemaResult.diffToPrevious.changes.into_iter()
+                .filter(|c| c.severity == "BREAKING")
+                .map(|c| c.description)
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct ExampleVariables {
+        #[serde(rename = "graphID")]
+        graph_id: String,
+    }
+
+    #[test]
+    fn query_with_variables_serializes_as_a_nested_object() {
+        let variables = ExampleVariables { graph_id: String::from("my-graph") };
+        let gql_query = GraphqlQuery { query: "query { me { id } }", variables: Some(variables) };
+        let body = serde_json::to_value(&gql_query).unwrap();
+        assert_eq!(body["variables"], serde_json::json!({ "graphID": "my-graph" }));
+    }
+
+    #[tokio::test]
+    async fn execute_batch_caps_concurrency_and_preserves_order() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let client = AsyncApolloCloudClient::new(String::from("http://example.invalid"), String::from("token"));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let operations: Vec<_> = (0..10usize).map(|i| {
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(now, Ordering::SeqCst);
+                sleep(Duration::from_millis(10)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok::<usize, ClientError>(i)
+            }
+        }).collect();
+
+        let results = client.execute_batch(3, operations).await;
+        let values: Vec<usize> = results.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(values, (0..10).collect::<Vec<_>>());
+        assert!(max_in_flight.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_from_the_base() {
+        assert_eq!(AsyncApolloCloudClient::backoff(0), Duration::from_millis(BASE_BACKOFF_MS));
+        assert_eq!(AsyncApolloCloudClient::backoff(1), Duration::from_millis(BASE_BACKOFF_MS * 2));
+        assert_eq!(AsyncApolloCloudClient::backoff(2), Duration::from_millis(BASE_BACKOFF_MS * 4));
+    }
+
+    #[test]
+    fn retry_after_parses_the_header_when_present() {
+        let http_response = http::Response::builder()
+            .header(reqwest::header::RETRY_AFTER, "7")
+            .body("")
+            .unwrap();
+        let response: reqwest::Response = http_response.into();
+        assert_eq!(AsyncApolloCloudClient::retry_after(&response), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn retry_after_is_none_when_missing_or_malformed() {
+        let missing = http::Response::builder().body("").unwrap();
+        assert_eq!(AsyncApolloCloudClient::retry_after(&missing.into()), None);
+
+        let malformed = http::Response::builder()
+            .header(reqwest::header::RETRY_AFTER, "not-a-number")
+            .body("")
+            .unwrap();
+        assert_eq!(AsyncApolloCloudClient::retry_after(&malformed.into()), None);
+    }
+}
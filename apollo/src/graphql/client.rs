@@ -1,126 +1,124 @@
-use serde_json::{Value, Map, Error};
-use reqwest::blocking::{Client, ClientBuilder};
-use serde::{Serialize, Deserialize};
-use std::collections::{HashMap, HashSet};
-use reqwest::header::{HeaderMap, HeaderValue};
-use std::vec::Vec;
-use std::iter::FromIterator;
-use serde::de::DeserializeOwned;
-use dirs::data_dir;
+use std::collections::HashSet;
+use thiserror::Error;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::auth::{IdentityConfig, OAuthTokens};
+use crate::credentials::{self, Credential, StoredAuth};
+use crate::graphql::async_client::AsyncApolloCloudClient;
 use crate::graphql::types::*;
 
-pub struct ApolloCloudClient {
-    endpoint_url: String,
-    client: Client,
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+    #[error("failed to (de)serialize graphql payload: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{}", .0.iter().map(|e| e.message.clone()).collect::<Vec<String>>().join("\n"))]
+    GraphQl(Vec<GraphError>),
+    #[error("could not authenticate; check that your auth token is up-to-date")]
+    Unauthorized,
+    #[error("response contained no data")]
+    NoData,
+    #[error("expected {graph_name} to be a federated graph, but it is a monolithic service")]
+    ExpectedFederatedGraph { graph_name: String },
+    #[error("{0}")]
+    NotFound(String),
+    #[error("login failed: {0}")]
+    OAuthFlowFailed(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
-pub struct GraphqlOperationError {
-    message: String,
-    user_error: bool,
+fn new_blocking_runtime() -> Runtime {
+    Builder::new_current_thread().enable_all().build().unwrap()
 }
 
-#[derive(Serialize)]
-struct GraphqlQuery<'a> {
-    query: &'a str,
-    variables: Option<&'a String>
+/// Blocking facade over [`AsyncApolloCloudClient`], for callers that don't
+/// want to pull in an async runtime themselves. New code that can await
+/// should prefer the async client directly.
+///
+/// Each instance owns a single-threaded Tokio runtime to drive its requests;
+/// do not construct or call one from inside an already-running Tokio runtime
+/// (e.g. `#[tokio::main]`), as `block_on` will panic.
+pub struct ApolloCloudClient {
+    inner: AsyncApolloCloudClient,
+    runtime: Runtime,
 }
 
 impl ApolloCloudClient {
     pub fn new(endpoint_url: String, auth_token: String) -> ApolloCloudClient {
-        let mut headers = HeaderMap::new();
-        headers.insert("X-API-KEY",
-                       HeaderValue::from_str(&auth_token).unwrap());
-        headers.insert("CONTENT-TYPE",
-                       HeaderValue::from_str("application/json").unwrap());
-
-        let client = ClientBuilder::new()
-            .default_headers(headers)
-            .build().unwrap();
-
         ApolloCloudClient {
-            endpoint_url,
-            client,
+            inner: AsyncApolloCloudClient::new(endpoint_url, auth_token),
+            runtime: new_blocking_runtime(),
         }
     }
 
-    fn send_query<T: DeserializeOwned>(&self, query: GraphqlQuery) -> Result<T, Error> {
-        let query_body = serde_json::to_string(&query).unwrap();
-        let res = match self.client.post(&self.endpoint_url)
-            .body(query_body).send() {
-            Ok(res) => res,
-            Err(e) => panic!(e)
-        };
+    /// Builds a client authenticated via an OAuth2/OIDC token pair obtained from
+    /// [`crate::auth::login`], refreshing them transparently as they near expiry.
+    pub fn from_oauth(endpoint_url: String, identity: IdentityConfig, tokens: OAuthTokens) -> ApolloCloudClient {
+        ApolloCloudClient {
+            inner: AsyncApolloCloudClient::from_oauth(endpoint_url, identity, tokens),
+            runtime: new_blocking_runtime(),
+        }
+    }
 
-        let text = String::from(res.text().unwrap());
-        match serde_json::from_str::<T>(&text) {
-            Ok(r) => Ok(r),
-            Err(e) => {
-                return Err(e);
+    /// Builds a client from a named credential profile, applying the `APOLLO_KEY`
+    /// environment override if it is set. See [`crate::credentials`].
+    pub fn from_profile(name: &str) -> Result<ApolloCloudClient, ClientError> {
+        let credential = credentials::load_profile(name)?;
+        Ok(match credential.auth {
+            StoredAuth::ApiKey { token } => ApolloCloudClient::new(credential.endpoint_url, token),
+            StoredAuth::OAuth { access_token, refresh_token, expires_at, client_id, authorization_endpoint, token_endpoint } => {
+                let identity = IdentityConfig { authorization_endpoint, token_endpoint, client_id };
+                let tokens = OAuthTokens { access_token, refresh_token, expires_at };
+                ApolloCloudClient::from_oauth(credential.endpoint_url, identity, tokens)
             }
-        }
+        })
     }
 
-    fn execute_operation<T: DeserializeOwned, V: Serialize>(&self, operation_string: &str, variables: V) -> Result<T, Error> {
-        let vars_string = serde_json::to_string(&variables).unwrap();
-        let gql_query = GraphqlQuery { query: operation_string, variables: Some(&vars_string)};
-        self.send_query::<T>(gql_query)
+    /// Builds a client from the `default` credential profile.
+    pub fn from_default_profile() -> Result<ApolloCloudClient, ClientError> {
+        ApolloCloudClient::from_profile(credentials::default_profile_name())
     }
 
-    fn execute_operation_no_variables<T: DeserializeOwned>(&self, operation_string: &str) -> Result<T, Error> {
-        let gql_query = GraphqlQuery { query: operation_string, variables: None};
-        self.send_query::<T>(gql_query)
+    /// Persists this client's current endpoint and credentials under `name`.
+    pub fn save_profile(&self, name: &str) -> Result<(), ClientError> {
+        let auth = self.runtime.block_on(self.inner.snapshot_auth());
+        credentials::save_profile(name, Credential { endpoint_url: self.endpoint_url().to_string(), auth })
     }
 
-    pub fn get_org_memberships(&self) -> Result<HashSet<String>, &str> {
-        let result = match self.execute_operation_no_variables::<GetOrgMembershipResponse>(
-            GET_ORG_MEMBERSHIPS_QUERY) {
-            Ok(r) => r,
-            Err(e) => {
-                println!("Encountered error {}", e);
-                return Err("Could not fetch organizations");
-            }
-        };
-        match result.data.unwrap().me {
-            Some(me) =>
-                Ok(
-                    HashSet::from_iter(
-                        me.memberships.into_iter().map(
-                            |it| it.account.id
-                        ).collect::<Vec<String>>())),
-            None => Err("Could not authenticate. Please check that your auth token is up-to-date"),
-        }
+    fn endpoint_url(&self) -> &str {
+        self.inner.endpoint_url()
     }
 
-    pub fn create_new_graph(&self, graph_id: String, account_id: String) -> Result<String, GraphqlOperationError> {
-        let variables = CreateGraphVariables {
-            graphID: graph_id,
-            accountID: account_id,
-        };
-        let result =
-            match self.execute_operation::<CreateGraphResponse, CreateGraphVariables>(CREATE_GRAPH_QUERY, variables) {
-                Ok(result) => result,
-                Err(message) => return Err(GraphqlOperationError { message: message.to_string(), user_error: false })
-            };
-        if result.errors.is_some() {
-            let message = result.errors.unwrap()
-                .iter_mut().map(| err| err.message.clone())
-                .collect::<Vec<String>>().join("\n");
-            return Err(GraphqlOperationError { message, user_error: false });
-        }
+    pub fn get_org_memberships(&self) -> Result<HashSet<String>, ClientError> {
+        self.runtime.block_on(self.inner.get_org_memberships())
+    }
+
+    pub fn create_new_graph(&self, graph_id: String, account_id: String) -> Result<String, ClientError> {
+        self.runtime.block_on(self.inner.create_new_graph(graph_id, account_id))
+    }
+
+    /// Fetches the SDL and routing URL for a single subgraph of a federated graph.
+    ///
+    /// Returns `ExpectedFederatedGraph` if `graph_id`/`variant` resolves to a
+    /// monolithic (non-federated) service instead of a federation.
+    pub fn fetch_subgraph_sdl(&self, graph_id: String, variant: String, subgraph_name: String) -> Result<SubgraphInfo, ClientError> {
+        self.runtime.block_on(self.inner.fetch_subgraph_sdl(graph_id, variant, subgraph_name))
+    }
 
-        let data = match result.data {
-            Some(data) => data,
-            None => return Err(GraphqlOperationError {
-                message: String::from("Got no data????"),
-                user_error: false,
-            })
-        };
+    /// Publishes a subgraph's schema so it can be composed into the supergraph.
+    pub fn publish_subgraph(&self, graph_id: String, variant: String, subgraph_name: String, url: String, sdl: String) -> Result<PublishSubgraphResult, ClientError> {
+        self.runtime.block_on(self.inner.publish_subgraph(graph_id, variant, subgraph_name, url, sdl))
+    }
 
-        Ok(data.newService.apiKeys[0].token.clone())
+    /// Submits a proposed subgraph schema for composition validation without publishing it.
+    pub fn check_subgraph(&self, graph_id: String, variant: String, subgraph_name: String, proposed_schema: String) -> Result<CheckSubgraphResult, ClientError> {
+        self.runtime.block_on(self.inner.check_subgraph(graph_id, variant, subgraph_name, proposed_schema))
     }
 }
 
-static GET_ORG_MEMBERSHIPS_QUERY: &'static str = "
+pub(crate) static GET_ORG_MEMBERSHIPS_QUERY: &str = "
 query GetOrgMemberships {
   me {
     ...on User {
@@ -134,7 +132,7 @@ query GetOrgMemberships {
 }
 ";
 
-static CREATE_GRAPH_QUERY: &'static str = "
+pub(crate) static CREATE_GRAPH_QUERY: &str = "
 mutation CreateGraph($accountID: ID!, $graphID: ID!) {
   newService(accountId: $accountID, id: $graphID) {
     id
@@ -143,4 +141,59 @@ mutation CreateGraph($accountID: ID!, $graphID: ID!) {
     }
   }
 }
-";
\ No newline at end of file
+";
+
+pub(crate) static FETCH_SUBGRAPH_SDL_QUERY: &str = "
+query FetchSubgraphSdl($serviceID: ID!, $graphVariant: String!) {
+  service(id: $serviceID) {
+    implementingServices(graphVariant: $graphVariant) {
+      __typename
+      ...on FederatedImplementingServices {
+        services {
+          name
+          url
+          activePartialSchema {
+            sdl
+          }
+        }
+      }
+    }
+  }
+}
+";
+
+pub(crate) static PUBLISH_SUBGRAPH_QUERY: &str = "
+mutation PublishSubgraph($graphID: ID!, $graphVariant: String!, $name: String!, $url: String!, $activePartialSchema: PartialSchemaInput!) {
+  publishSubgraph(graphId: $graphID, graphVariant: $graphVariant, name: $name, url: $url, activePartialSchema: $activePartialSchema) {
+    compositionConfig {
+      schemaHash
+    }
+    errors {
+      message
+    }
+    updated
+  }
+}
+";
+
+pub(crate) static CHECK_SUBGRAPH_QUERY: &str = "
+query CheckSubgraph($graphID: ID!, $graphVariant: String!, $name: String!, $proposedSchema: String!) {
+  service(id: $graphID) {
+    checkPartialSchema(graphVariant: $graphVariant, name: $name, proposedSchema: $proposedSchema) {
+      compositionValidationResult {
+        errors {
+          message
+        }
+      }
+      checkSchemaResult {
+        diffToPrevious {
+          changes {
+            severity
+            description
+          }
+        }
+      }
+    }
+  }
+}
+";
@@ -0,0 +1,207 @@
+use serde::{Serialize, Deserialize};
+
+/// Generic envelope every GraphQL response is deserialized into before the
+/// client unwraps it into an operation-specific result or `ClientError`.
+#[derive(Deserialize)]
+pub struct GraphResult<T> {
+    pub data: Option<T>,
+    #[serde(default)]
+    pub errors: Vec<GraphError>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GraphError {
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct MeData {
+    pub me: Option<MeUser>,
+}
+
+#[derive(Deserialize)]
+pub struct MeUser {
+    pub memberships: Vec<Membership>,
+}
+
+#[derive(Deserialize)]
+pub struct Membership {
+    pub account: Account,
+}
+
+#[derive(Deserialize)]
+pub struct Account {
+    pub id: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize)]
+pub struct CreateGraphVariables {
+    pub graphID: String,
+    pub accountID: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub struct CreateGraphData {
+    pub newService: NewService,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub struct NewService {
+    pub apiKeys: Vec<ApiKey>,
+}
+
+#[derive(Deserialize)]
+pub struct ApiKey {
+    pub token: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize)]
+pub struct FetchSubgraphSdlVariables {
+    pub serviceID: String,
+    pub graphVariant: String,
+}
+
+#[derive(Deserialize)]
+pub struct FetchSubgraphSdlData {
+    pub service: Option<ServiceWrapper>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub struct ServiceWrapper {
+    pub implementingServices: Option<ImplementingServicesWrapper>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "__typename")]
+pub enum ImplementingServicesWrapper {
+    FederatedImplementingServices { services: Vec<ImplementingServiceDetails> },
+    NonFederatedImplementingService {},
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Clone)]
+pub struct ImplementingServiceDetails {
+    pub name: String,
+    pub url: Option<String>,
+    pub activePartialSchema: ActivePartialSchema,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ActivePartialSchema {
+    pub sdl: String,
+}
+
+/// A single federated subgraph as reported by the registry.
+pub struct SubgraphInfo {
+    pub name: String,
+    pub url: Option<String>,
+    pub sdl: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize)]
+pub struct PublishSubgraphVariables {
+    pub graphID: String,
+    pub graphVariant: String,
+    pub name: String,
+    pub url: String,
+    pub activePartialSchema: ActivePartialSchemaInput,
+}
+
+#[derive(Serialize)]
+pub struct ActivePartialSchemaInput {
+    pub sdl: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub struct PublishSubgraphData {
+    pub publishSubgraph: PublishSubgraphMutationResult,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub struct PublishSubgraphMutationResult {
+    pub compositionConfig: Option<CompositionConfig>,
+    pub errors: Vec<CompositionError>,
+    pub updated: bool,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub struct CompositionConfig {
+    pub schemaHash: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct CompositionError {
+    pub message: String,
+}
+
+/// Outcome of publishing a subgraph's schema for composition.
+pub struct PublishSubgraphResult {
+    pub updated: bool,
+    pub composition_errors: Vec<String>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize)]
+pub struct CheckSubgraphVariables {
+    pub graphID: String,
+    pub graphVariant: String,
+    pub name: String,
+    pub proposedSchema: String,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub struct CheckSubgraphData {
+    pub service: Option<CheckSubgraphServiceWrapper>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub struct CheckSubgraphServiceWrapper {
+    pub checkPartialSchema: CheckPartialSchemaResult,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub struct CheckPartialSchemaResult {
+    pub compositionValidationResult: CompositionValidationResult,
+    pub checkSchemaResult: CheckSchemaResult,
+}
+
+#[derive(Deserialize)]
+pub struct CompositionValidationResult {
+    pub errors: Vec<CompositionError>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+pub struct CheckSchemaResult {
+    pub diffToPrevious: DiffToPrevious,
+}
+
+#[derive(Deserialize)]
+pub struct DiffToPrevious {
+    pub changes: Vec<SchemaChange>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct SchemaChange {
+    pub severity: String,
+    pub description: String,
+}
+
+/// Result of validating a proposed subgraph schema against the supergraph,
+/// without publishing it.
+pub struct CheckSubgraphResult {
+    pub composition_errors: Vec<String>,
+    pub breaking_changes: Vec<String>,
+}